@@ -1,48 +1,1052 @@
+use std::collections::HashSet;
 use std::env;
 use std::fs;
-use syn::{visit_mut::VisitMut, File, Item, ItemFn, FnArg, Pat};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
+    execute,
+    terminal::{self, ClearType},
+};
+use quote::ToTokens;
+use syn::spanned::Spanned;
+use syn::{
+    visit::Visit, visit_mut::VisitMut, File, FnArg, Item, ItemFn, ItemUse, Member, Pat,
+    Path as SynPath, UseGroup, UseName, UseRename, UseTree, Visibility,
+};
 
-struct Cleaner;
+/// Collects every identifier the file actually *uses* (as opposed to
+/// imports), so the import pruner can tell a live binding from a dead one.
+///
+/// `use` items themselves are skipped entirely: `visit_item_use` is a no-op,
+/// which stops the default traversal from walking into the very paths we're
+/// deciding whether to keep.
+#[derive(Default)]
+struct UsageCollector {
+    used: HashSet<String>,
+}
+
+/// Recursively collect every identifier token in a macro's argument tokens
+/// (descending into groups, e.g. the `(...)` of `println!(...)`). Macro
+/// bodies are opaque `TokenStream`s to `syn` -- this is the only way to see
+/// the names used inside one without a full macro expander.
+fn idents_in_token_stream(tokens: &proc_macro2::TokenStream, out: &mut Vec<String>) {
+    for tt in tokens.clone() {
+        match tt {
+            proc_macro2::TokenTree::Ident(ident) => out.push(ident.to_string()),
+            proc_macro2::TokenTree::Group(group) => idents_in_token_stream(&group.stream(), out),
+            proc_macro2::TokenTree::Punct(_) | proc_macro2::TokenTree::Literal(_) => {}
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for UsageCollector {
+    fn visit_item_use(&mut self, _i: &'ast ItemUse) {
+        // Don't count the names a `use` introduces as uses of themselves.
+    }
+
+    fn visit_path(&mut self, path: &'ast SynPath) {
+        for segment in &path.segments {
+            self.used.insert(segment.ident.to_string());
+        }
+        syn::visit::visit_path(self, path);
+    }
+
+    fn visit_member(&mut self, member: &'ast Member) {
+        if let Member::Named(ident) = member {
+            self.used.insert(ident.to_string());
+        }
+        syn::visit::visit_member(self, member);
+    }
+
+    fn visit_expr_method_call(&mut self, call: &'ast syn::ExprMethodCall) {
+        self.used.insert(call.method.to_string());
+        syn::visit::visit_expr_method_call(self, call);
+    }
+
+    fn visit_macro(&mut self, mac: &'ast syn::Macro) {
+        let mut idents = Vec::new();
+        idents_in_token_stream(&mac.tokens, &mut idents);
+        self.used.extend(idents);
+        syn::visit::visit_macro(self, mac);
+    }
+}
+
+/// The name a `use` leaf actually binds into scope. A bare `self` leaf
+/// inside a group (`use std::io::{self, Write}`) doesn't bind something
+/// named `self` -- it binds the enclosing module's own name (`io`), so it
+/// must resolve against `parent_ident` rather than the literal ident text.
+fn leaf_binding_name(ident: &syn::Ident, parent_ident: Option<&str>) -> String {
+    if ident == "self" {
+        parent_ident.unwrap_or("self").to_string()
+    } else {
+        ident.to_string()
+    }
+}
+
+/// Whether a `use` leaf bound to `name` should survive pruning.
+///
+/// Beyond a direct reference, a name that looks like a trait or type
+/// (`UpperCamelCase`) is also kept even if `used` never mentions it.
+/// `UsageCollector` only sees identifiers that are written out -- it has no
+/// type information, so it can't tell that `use std::io::Write;` is needed
+/// by a later `.flush()` call (trait-method resolution) or that
+/// `use std::fmt::Write as FmtWrite;` is needed by a `write!` call (macro
+/// expansion implying a trait bound). Since we can't prove such an import
+/// unused from syntax alone, we don't delete it.
+fn keep_use_leaf(name: &str, used: &HashSet<String>) -> bool {
+    used.contains(name) || name.starts_with(|c: char| c.is_uppercase())
+}
+
+/// Prune the leaves of a `use` tree that `used` never references, preserving
+/// the original ordering of whatever survives.
+///
+/// Returns `None` when nothing in `tree` is referenced, in which case the
+/// caller should drop the whole `use` item.
+fn prune_use_tree(
+    tree: &UseTree,
+    used: &HashSet<String>,
+    parent_ident: Option<&str>,
+) -> Option<UseTree> {
+    match tree {
+        UseTree::Glob(_) => Some(tree.clone()),
+        UseTree::Name(UseName { ident }) => {
+            if keep_use_leaf(&leaf_binding_name(ident, parent_ident), used) {
+                Some(tree.clone())
+            } else {
+                None
+            }
+        }
+        UseTree::Rename(UseRename { rename, .. }) => {
+            if keep_use_leaf(&rename.to_string(), used) {
+                Some(tree.clone())
+            } else {
+                None
+            }
+        }
+        UseTree::Path(path) => {
+            let segment = path.ident.to_string();
+            let inner = prune_use_tree(&path.tree, used, Some(&segment))?;
+            let mut pruned = path.clone();
+            *pruned.tree = inner;
+            Some(UseTree::Path(pruned))
+        }
+        UseTree::Group(UseGroup { brace_token, items }) => {
+            // Insertion-ordered: walk leaves in their original order so
+            // survivors come out in the order they were written.
+            let survivors: Vec<UseTree> = items
+                .iter()
+                .filter_map(|leaf| prune_use_tree(leaf, used, parent_ident))
+                .collect();
+            match survivors.len() {
+                0 => None,
+                // A single survivor collapses the group back to a plain path,
+                // e.g. `use a::{b, c}` with only `b` used becomes `use a::b`.
+                1 => Some(survivors.into_iter().next().unwrap()),
+                _ => Some(UseTree::Group(UseGroup {
+                    brace_token: *brace_token,
+                    items: survivors.into_iter().collect(),
+                })),
+            }
+        }
+    }
+}
+
+/// A single proposed edit, identified by the byte range of the syntax node
+/// it touches so CI output and editors can point straight at the location.
+///
+/// This stores a resolved `start`/`end` rather than the `proc_macro2::Span`
+/// itself: `Span` is `!Send`, and suggestions are built on worker threads
+/// (see `clean_in_parallel`) then sent back to the main thread over an
+/// `mpsc` channel, which requires `Suggestion` — and therefore `FileResult`
+/// — to be `Send`.
+struct Suggestion {
+    start: usize,
+    end: usize,
+    original: String,
+    replacement: String,
+    message: String,
+}
+
+impl Suggestion {
+    fn byte_range(&self) -> std::ops::Range<usize> {
+        self.start..self.end
+    }
+
+    fn to_json(&self) -> String {
+        format!(
+            "{{\"start\":{},\"end\":{},\"original\":{},\"replacement\":{},\"message\":{}}}",
+            self.start,
+            self.end,
+            json_string(&self.original),
+            json_string(&self.replacement),
+            json_string(&self.message),
+        )
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Collects every bound identifier in a parameter pattern, so tuple/struct
+/// destructured parameters (`(a, b): (i32, i32)`, `Point { x, y }: Point`)
+/// get each sub-binding checked and renamed independently instead of as one
+/// all-or-nothing unit.
+fn collect_binding_idents<'p>(pat: &'p mut Pat, out: &mut Vec<&'p mut syn::Ident>) {
+    match pat {
+        Pat::Ident(pi) => {
+            if let Some((_, sub)) = &mut pi.subpat {
+                collect_binding_idents(sub, out);
+            }
+            out.push(&mut pi.ident);
+        }
+        Pat::Tuple(t) => {
+            for elem in t.elems.iter_mut() {
+                collect_binding_idents(elem, out);
+            }
+        }
+        Pat::TupleStruct(t) => {
+            for elem in t.elems.iter_mut() {
+                collect_binding_idents(elem, out);
+            }
+        }
+        Pat::Struct(s) => {
+            for field in s.fields.iter_mut() {
+                collect_binding_idents(&mut field.pat, out);
+            }
+        }
+        Pat::Reference(r) => collect_binding_idents(&mut r.pat, out),
+        Pat::Type(t) => collect_binding_idents(&mut t.pat, out),
+        _ => {}
+    }
+}
+
+/// True if `pat` introduces a new binding named `target`, i.e. a `let`
+/// using this pattern would shadow an outer variable of that name.
+fn pattern_shadows(pat: &Pat, target: &str) -> bool {
+    match pat {
+        Pat::Ident(pi) => {
+            pi.ident == target || pi.subpat.as_ref().is_some_and(|(_, sub)| pattern_shadows(sub, target))
+        }
+        Pat::Tuple(t) => t.elems.iter().any(|elem| pattern_shadows(elem, target)),
+        Pat::TupleStruct(t) => t.elems.iter().any(|elem| pattern_shadows(elem, target)),
+        Pat::Struct(s) => s.fields.iter().any(|field| pattern_shadows(&field.pat, target)),
+        Pat::Reference(r) => pattern_shadows(&r.pat, target),
+        Pat::Type(t) => pattern_shadows(&t.pat, target),
+        Pat::Or(o) => o.cases.iter().any(|case| pattern_shadows(case, target)),
+        _ => false,
+    }
+}
+
+/// Finds whether `target` is ever referenced as a plain local (an
+/// `ExprPath` with a single segment), correctly ignoring references that
+/// actually resolve to a `let` binding that shadows it.
+///
+/// Shadowing is tracked with a stack of booleans, one per lexical block:
+/// entering a block inherits whether `target` is currently shadowed, a
+/// `let` that rebinds the name flips the top of the stack for the rest of
+/// that block, and leaving the block restores the outer state. A `let`'s
+/// initializer is visited *before* the rebinding takes effect, since the
+/// right-hand side still refers to the outer name.
+struct ParamUsageVisitor<'a> {
+    target: &'a str,
+    shadowed: Vec<bool>,
+    used: bool,
+}
+
+impl<'a> ParamUsageVisitor<'a> {
+    fn new(target: &'a str) -> Self {
+        ParamUsageVisitor {
+            target,
+            shadowed: vec![false],
+            used: false,
+        }
+    }
+
+    fn is_shadowed(&self) -> bool {
+        *self.shadowed.last().unwrap_or(&false)
+    }
+}
+
+impl<'a, 'ast> Visit<'ast> for ParamUsageVisitor<'a> {
+    fn visit_block(&mut self, block: &'ast syn::Block) {
+        let inherited = self.is_shadowed();
+        self.shadowed.push(inherited);
+        for stmt in &block.stmts {
+            if let syn::Stmt::Local(local) = stmt {
+                if let Some(init) = &local.init {
+                    self.visit_expr(&init.expr);
+                    if let Some((_, diverge)) = &init.diverge {
+                        self.visit_expr(diverge);
+                    }
+                }
+                if pattern_shadows(&local.pat, self.target) {
+                    *self.shadowed.last_mut().unwrap() = true;
+                }
+            } else {
+                syn::visit::visit_stmt(self, stmt);
+            }
+        }
+        self.shadowed.pop();
+    }
+
+    fn visit_expr_path(&mut self, path: &'ast syn::ExprPath) {
+        if !self.is_shadowed() && path.path.segments.len() == 1 && path.path.segments[0].ident == self.target {
+            self.used = true;
+        }
+        syn::visit::visit_expr_path(self, path);
+    }
+
+    fn visit_macro(&mut self, mac: &'ast syn::Macro) {
+        // Macro arguments are opaque tokens to `syn`, not parsed exprs, so a
+        // parameter used only inside `println!("{x}")`, `assert_eq!(x, ..)`,
+        // etc. would otherwise look unused and get wrongly renamed.
+        if !self.is_shadowed() {
+            let mut idents = Vec::new();
+            idents_in_token_stream(&mac.tokens, &mut idents);
+            if idents.iter().any(|ident| ident == self.target) {
+                self.used = true;
+            }
+        }
+        syn::visit::visit_macro(self, mac);
+    }
+}
+
+struct Cleaner {
+    used: HashSet<String>,
+    suggestions: Vec<Suggestion>,
+}
 
 impl VisitMut for Cleaner {
     fn visit_item_fn_mut(&mut self, i: &mut ItemFn) {
-        // Prefix unused arguments with _
+        // Prefix unused arguments with _, checking each bound sub-identifier
+        // of a (possibly destructured) parameter pattern independently.
         for input in i.sig.inputs.iter_mut() {
             if let FnArg::Typed(pat_type) = input {
-                if let Pat::Ident(ident) = &mut *pat_type.pat {
-                    let name = ident.ident.to_string();
-                    if !i.block.stmts.iter().any(|stmt| stmt.to_token_stream().to_string().contains(&name)) {
-                        ident.ident = syn::Ident::new(&format!("_{}", name), ident.ident.span());
+                let mut bindings = Vec::new();
+                collect_binding_idents(&mut pat_type.pat, &mut bindings);
+                for ident in bindings {
+                    let name = ident.to_string();
+                    if name == "self" || name.starts_with('_') {
+                        continue;
+                    }
+                    let mut usage = ParamUsageVisitor::new(&name);
+                    usage.visit_block(&i.block);
+                    if !usage.used {
+                        let replacement = format!("_{}", name);
+                        let range = ident.span().byte_range();
+                        self.suggestions.push(Suggestion {
+                            start: range.start,
+                            end: range.end,
+                            original: name.clone(),
+                            replacement: replacement.clone(),
+                            message: format!("unused parameter `{}` renamed to `{}`", name, replacement),
+                        });
+                        *ident = syn::Ident::new(&replacement, ident.span());
                     }
                 }
             }
         }
         syn::visit_mut::visit_item_fn_mut(self, i);
     }
+
     fn visit_item_mut(&mut self, item: &mut Item) {
-        // Remove unused imports (simple heuristic: any use not used in code)
         if let Item::Use(u) = item {
-            let path = u.tree.to_token_stream().to_string();
-            // crude: remove if not used elsewhere
-            // For best results, use a linter (like rust-analyzer) or enhance this logic
-            if !path.contains("solana_program") && !path.contains("solana_sdk") {
-                *item = Item::Verbatim(proc_macro2::TokenStream::new());
+            // `pub use` is a re-export: always keep it, regardless of
+            // whether this file uses the name itself.
+            if matches!(u.vis, Visibility::Inherited) {
+                let original = u.to_token_stream().to_string();
+                let range = u.span().byte_range();
+                match prune_use_tree(&u.tree, &self.used, None) {
+                    Some(pruned) => {
+                        if pruned.to_token_stream().to_string() != u.tree.to_token_stream().to_string() {
+                            u.tree = pruned;
+                            self.suggestions.push(Suggestion {
+                                start: range.start,
+                                end: range.end,
+                                original,
+                                replacement: u.to_token_stream().to_string(),
+                                message: "unused import leaves removed".to_string(),
+                            });
+                        }
+                    }
+                    None => {
+                        self.suggestions.push(Suggestion {
+                            start: range.start,
+                            end: range.end,
+                            original,
+                            replacement: String::new(),
+                            message: "unused import removed".to_string(),
+                        });
+                        *item = Item::Verbatim(proc_macro2::TokenStream::new());
+                    }
+                }
             }
         }
         syn::visit_mut::visit_item_mut(self, item);
     }
 }
 
+/// Names of directories we never descend into while discovering `.rs` files.
+const SKIP_DIRS: &[&str] = &["target", "vendor", ".git", "node_modules"];
+
+/// Recursively collect every `.rs` file under `root`, skipping build output and
+/// vendored dependency directories.
+fn discover_rs_files(root: &Path, out: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    if root.is_file() {
+        if root.extension().is_some_and(|ext| ext == "rs") {
+            out.push(root.to_path_buf());
+        }
+        return Ok(());
+    }
+    for entry in fs::read_dir(root)? {
+        let entry = entry?;
+        let path = entry.path();
+        if path.is_dir() {
+            let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+            if SKIP_DIRS.contains(&name) {
+                continue;
+            }
+            discover_rs_files(&path, out)?;
+        } else if path.extension().is_some_and(|ext| ext == "rs") {
+            out.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Outcome of cleaning a single file, reported back to the main thread.
+enum FileResult {
+    Cleaned,
+    DryRun {
+        path: PathBuf,
+        suggestions: Vec<Suggestion>,
+        diff: String,
+    },
+    ParseError(PathBuf, String),
+    /// Never reached because an interrupt arrived before its turn.
+    Skipped(PathBuf),
+}
+
+/// Write `contents` to `path` crash-safely: stage it in a temp file next to
+/// the target (so the final rename stays on the same filesystem), preserve
+/// the original file's permissions, then atomically rename over it. A
+/// process killed mid-write leaves either the untouched original or a stray
+/// `.tmp` file next to it -- never a truncated target.
+fn atomic_write(path: &Path, contents: &str) -> io::Result<()> {
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path has no file name"))?;
+    let tmp_path = dir.join(format!(
+        ".{}.clean-rust.tmp.{}",
+        file_name.to_string_lossy(),
+        std::process::id()
+    ));
+
+    let original_permissions = fs::metadata(path).ok().map(|m| m.permissions());
+
+    let write_result = fs::write(&tmp_path, contents).and_then(|()| {
+        if let Some(permissions) = original_permissions {
+            fs::set_permissions(&tmp_path, permissions)?;
+        }
+        fs::rename(&tmp_path, path)
+    });
+    if write_result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+    write_result
+}
+
+/// The result of running `Cleaner` over a file's source without writing
+/// anything back: the original text, the proposed edits, and what the file
+/// would look like with every edit applied.
+struct Analysis {
+    src: String,
+    suggestions: Vec<Suggestion>,
+    cleaned: String,
+}
+
+fn analyze(path: &Path) -> Result<Analysis, String> {
+    let src = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let mut syntax: File = syn::parse_file(&src).map_err(|e| e.to_string())?;
+    let mut usage = UsageCollector::default();
+    usage.visit_file(&syntax);
+    let mut cleaner = Cleaner {
+        used: usage.used,
+        suggestions: Vec::new(),
+    };
+    cleaner.visit_file_mut(&mut syntax);
+    let cleaned = prettyplease::unparse(&syntax);
+    Ok(Analysis {
+        src,
+        suggestions: cleaner.suggestions,
+        cleaned,
+    })
+}
+
+fn clean_file(path: &Path, dry_run: bool) -> FileResult {
+    let analysis = match analyze(path) {
+        Ok(a) => a,
+        Err(e) => return FileResult::ParseError(path.to_path_buf(), e),
+    };
+    if dry_run {
+        return FileResult::DryRun {
+            diff: unified_diff(&analysis.src, &analysis.cleaned, path),
+            suggestions: analysis.suggestions,
+            path: path.to_path_buf(),
+        };
+    }
+    match atomic_write(path, &analysis.cleaned) {
+        Ok(()) => FileResult::Cleaned,
+        Err(e) => FileResult::ParseError(path.to_path_buf(), e.to_string()),
+    }
+}
+
+/// Tag each line of `a`/`b` as unchanged (` `), removed (`-`), or added
+/// (`+`) using a straightforward LCS-based line diff.
+fn diff_lines(a: &[&str], b: &[&str]) -> Vec<(char, String)> {
+    let n = a.len();
+    let m = b.len();
+    let mut dp = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            dp[i][j] = if a[i] == b[j] {
+                dp[i + 1][j + 1] + 1
+            } else {
+                dp[i + 1][j].max(dp[i][j + 1])
+            };
+        }
+    }
+    let mut out = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if a[i] == b[j] {
+            out.push((' ', a[i].to_string()));
+            i += 1;
+            j += 1;
+        } else if dp[i + 1][j] >= dp[i][j + 1] {
+            out.push(('-', a[i].to_string()));
+            i += 1;
+        } else {
+            out.push(('+', b[j].to_string()));
+            j += 1;
+        }
+    }
+    out.extend(a[i..].iter().map(|line| ('-', line.to_string())));
+    out.extend(b[j..].iter().map(|line| ('+', line.to_string())));
+    out
+}
+
+/// Render a standard `diff -u`-style unified diff between `original` and
+/// `modified`, with three lines of context around each change.
+fn unified_diff(original: &str, modified: &str, path: &Path) -> String {
+    const CONTEXT: usize = 3;
+    let a: Vec<&str> = original.lines().collect();
+    let b: Vec<&str> = modified.lines().collect();
+    let tags = diff_lines(&a, &b);
+    if tags.iter().all(|(tag, _)| *tag == ' ') {
+        return String::new();
+    }
+
+    let n = tags.len();
+    let mut include = vec![false; n];
+    for (idx, (tag, _)) in tags.iter().enumerate() {
+        if *tag != ' ' {
+            let lo = idx.saturating_sub(CONTEXT);
+            let hi = (idx + CONTEXT + 1).min(n);
+            include[lo..hi].iter_mut().for_each(|flag| *flag = true);
+        }
+    }
+
+    let mut out = format!("--- a/{}\n+++ b/{}\n", path.display(), path.display());
+    let mut idx = 0;
+    while idx < n {
+        if !include[idx] {
+            idx += 1;
+            continue;
+        }
+        let hunk_start = idx;
+        let mut hunk_end = idx;
+        while hunk_end < n && include[hunk_end] {
+            hunk_end += 1;
+        }
+
+        let mut orig_line = 1usize;
+        let mut new_line = 1usize;
+        for (tag, _) in &tags[..hunk_start] {
+            match tag {
+                ' ' => {
+                    orig_line += 1;
+                    new_line += 1;
+                }
+                '-' => orig_line += 1,
+                _ => new_line += 1,
+            }
+        }
+        let mut orig_count = 0usize;
+        let mut new_count = 0usize;
+        for (tag, _) in &tags[hunk_start..hunk_end] {
+            match tag {
+                ' ' => {
+                    orig_count += 1;
+                    new_count += 1;
+                }
+                '-' => orig_count += 1,
+                _ => new_count += 1,
+            }
+        }
+        out.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            orig_line, orig_count, new_line, new_count
+        ));
+        for (tag, line) in &tags[hunk_start..hunk_end] {
+            out.push(*tag);
+            out.push_str(line);
+            out.push('\n');
+        }
+        idx = hunk_end;
+    }
+    out
+}
+
+/// What the user chose for a single proposed hunk.
+enum Decision {
+    Accept,
+    Skip,
+    Quit,
+}
+
+/// Puts the terminal into raw mode for the life of an interactive session
+/// and restores it on drop, including on panic or early return (e.g. a
+/// terminal I/O error partway through a session).
+struct RawModeGuard;
+
+impl RawModeGuard {
+    fn enable() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        Ok(RawModeGuard)
+    }
+}
+
+impl Drop for RawModeGuard {
+    fn drop(&mut self) {
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// Raw mode doesn't translate `\n` to `\r\n`, so every line printed during
+/// an interactive session goes through this instead of `println!`.
+fn writeln_raw(stdout: &mut io::Stdout, line: &str) -> io::Result<()> {
+    write!(stdout, "{}\r\n", line)?;
+    stdout.flush()
+}
+
+/// Block for the next keypress, folding Ctrl-C into the same "quit" key the
+/// `q` prompt uses so a interrupt during review never leaves a half-applied
+/// file behind.
+fn read_key() -> io::Result<KeyCode> {
+    loop {
+        if let Event::Key(KeyEvent { code, modifiers, .. }) = event::read()? {
+            if code == KeyCode::Char('c') && modifiers.contains(KeyModifiers::CONTROL) {
+                return Ok(KeyCode::Char('q'));
+            }
+            return Ok(code);
+        }
+    }
+}
+
+/// Break a line into `width`-wide chunks so it never runs off a narrow
+/// terminal.
+fn wrap_line(line: &str, width: usize) -> Vec<String> {
+    if line.is_empty() {
+        return vec![String::new()];
+    }
+    line.chars()
+        .collect::<Vec<char>>()
+        .chunks(width.max(1))
+        .map(|chunk| chunk.iter().collect())
+        .collect()
+}
+
+/// The 1-based line number containing `byte_offset` in `src`.
+fn line_at(src: &str, byte_offset: usize) -> usize {
+    src[..byte_offset.min(src.len())].matches('\n').count() + 1
+}
+
+/// A few lines of source around the line a suggestion's byte range starts
+/// on, for orientation when the hunk itself is just a span and a
+/// replacement.
+fn context_lines(src: &str, suggestion: &Suggestion, radius: usize) -> Vec<String> {
+    let target_line = line_at(src, suggestion.start);
+    let lines: Vec<&str> = src.lines().collect();
+    let lo = target_line.saturating_sub(radius + 1);
+    let hi = (target_line + radius).min(lines.len());
+    lines[lo..hi]
+        .iter()
+        .enumerate()
+        .map(|(i, line)| format!("{:>5} | {}", lo + i + 1, line))
+        .collect()
+}
+
+/// Show one proposed edit full-screen, paging through it if it's taller than
+/// the terminal, and return what the user decided.
+fn prompt_hunk(
+    stdout: &mut io::Stdout,
+    path: &Path,
+    idx: usize,
+    total: usize,
+    src: &str,
+    suggestion: &Suggestion,
+) -> io::Result<Decision> {
+    let (width, height) = terminal::size().unwrap_or((80, 24));
+    let width = width.max(20) as usize;
+    let height = height.max(6) as usize;
+
+    let mut lines = vec![
+        format!("[{}/{}] {}", idx + 1, total, path.display()),
+        suggestion.message.clone(),
+        String::new(),
+        "-- context --".to_string(),
+    ];
+    lines.extend(context_lines(src, suggestion, 2));
+    lines.push(String::new());
+    lines.push(format!("- {}", suggestion.original));
+    lines.push(format!("+ {}", suggestion.replacement));
+
+    let wrapped: Vec<String> = lines.iter().flat_map(|line| wrap_line(line, width)).collect();
+
+    // One line is reserved for the prompt itself.
+    let page_size = height.saturating_sub(1).max(1);
+    let mut offset = 0;
+    loop {
+        execute!(stdout, terminal::Clear(ClearType::All), cursor::MoveTo(0, 0))?;
+        for line in wrapped.iter().skip(offset).take(page_size) {
+            write!(stdout, "{}\r\n", line)?;
+        }
+        let more = offset + page_size < wrapped.len();
+        if more {
+            write!(stdout, "-- more: space for next page, y/n/q decides now --")?;
+        } else {
+            write!(stdout, "Accept this change? [y/n/q] ")?;
+        }
+        stdout.flush()?;
+
+        match read_key()? {
+            KeyCode::Char(' ') if more => offset += page_size,
+            KeyCode::Char('y') | KeyCode::Char('Y') => return Ok(Decision::Accept),
+            KeyCode::Char('n') | KeyCode::Char('N') => return Ok(Decision::Skip),
+            KeyCode::Char('q') | KeyCode::Char('Q') => return Ok(Decision::Quit),
+            _ => {}
+        }
+    }
+}
+
+/// Apply only the accepted suggestions to `src`, working back-to-front so
+/// that earlier byte offsets stay valid as later ones are rewritten.
+fn apply_accepted(src: &str, accepted: &[&Suggestion]) -> String {
+    let mut ordered: Vec<&&Suggestion> = accepted.iter().collect();
+    ordered.sort_by_key(|s| std::cmp::Reverse(s.start));
+    let mut out = src.to_string();
+    for suggestion in ordered {
+        out.replace_range(suggestion.byte_range(), &suggestion.replacement);
+    }
+    out
+}
+
+/// Page through every file's suggestions one hunk at a time, `git add -p`
+/// style, applying only what the user accepts and reformatting the result.
+fn run_interactive(paths: &[PathBuf]) -> io::Result<()> {
+    let _raw = RawModeGuard::enable()?;
+    let mut stdout = io::stdout();
+
+    'files: for path in paths {
+        let analysis = match analyze(path) {
+            Ok(a) => a,
+            Err(e) => {
+                writeln_raw(&mut stdout, &format!("skipping {}: {}", path.display(), e))?;
+                continue;
+            }
+        };
+        if analysis.suggestions.is_empty() {
+            continue;
+        }
+
+        writeln_raw(
+            &mut stdout,
+            &format!("=== {} ({} suggestion(s)) ===", path.display(), analysis.suggestions.len()),
+        )?;
+
+        let mut accepted: Vec<&Suggestion> = Vec::new();
+        for (idx, suggestion) in analysis.suggestions.iter().enumerate() {
+            match prompt_hunk(&mut stdout, path, idx, analysis.suggestions.len(), &analysis.src, suggestion)? {
+                Decision::Accept => accepted.push(suggestion),
+                Decision::Skip => {}
+                Decision::Quit => break 'files,
+            }
+        }
+
+        if accepted.is_empty() {
+            continue;
+        }
+        let patched = apply_accepted(&analysis.src, &accepted);
+        match syn::parse_file(&patched) {
+            Ok(syntax) => {
+                let cleaned = prettyplease::unparse(&syntax);
+                if let Err(e) = atomic_write(path, &cleaned) {
+                    writeln_raw(&mut stdout, &format!("failed to write {}: {}", path.display(), e))?;
+                }
+            }
+            Err(e) => writeln_raw(&mut stdout, &format!("failed to reformat {}: {}", path.display(), e))?,
+        }
+    }
+    Ok(())
+}
+
+/// Install a Ctrl-C handler that flips a shared flag instead of killing the
+/// process outright, so an in-flight batch can finish the file it's
+/// currently writing and cleanly stop picking up new ones.
+fn install_interrupt_flag() -> Arc<AtomicBool> {
+    let interrupted = Arc::new(AtomicBool::new(false));
+    let flag = interrupted.clone();
+    let _ = ctrlc::set_handler(move || {
+        flag.store(true, Ordering::SeqCst);
+    });
+    interrupted
+}
+
+/// Split `files` into `jobs` roughly-equal chunks and clean each chunk on its
+/// own thread, joining all of them before returning the aggregated results.
+/// Once `interrupted` is set, each worker finishes the file it's on (atomic
+/// writes mean that can never leave a truncated file) and reports every
+/// remaining file in its chunk as `Skipped` rather than starting it.
+fn clean_in_parallel(
+    files: Vec<PathBuf>,
+    jobs: usize,
+    dry_run: bool,
+    interrupted: Arc<AtomicBool>,
+) -> Vec<FileResult> {
+    let jobs = jobs.max(1);
+    let chunk_size = (files.len() + jobs - 1) / jobs.max(1);
+    if chunk_size == 0 {
+        return Vec::new();
+    }
+    let (tx, rx) = mpsc::channel();
+    let mut handles = Vec::new();
+    for chunk in files.chunks(chunk_size.max(1)) {
+        let chunk = chunk.to_vec();
+        let tx = tx.clone();
+        let interrupted = interrupted.clone();
+        handles.push(thread::spawn(move || {
+            for path in chunk {
+                if interrupted.load(Ordering::SeqCst) {
+                    let _ = tx.send(FileResult::Skipped(path));
+                    continue;
+                }
+                let _ = tx.send(clean_file(&path, dry_run));
+            }
+        }));
+    }
+    drop(tx);
+    let results: Vec<FileResult> = rx.into_iter().collect();
+    for handle in handles {
+        let _ = handle.join();
+    }
+    results
+}
+
+fn parse_jobs_flag(args: &[String]) -> usize {
+    args.iter()
+        .position(|a| a == "--jobs")
+        .and_then(|i| args.get(i + 1))
+        .and_then(|v| v.parse().ok())
+        .unwrap_or_else(|| thread::available_parallelism().map(|n| n.get()).unwrap_or(1))
+}
+
+fn has_flag(args: &[String], flag: &str) -> bool {
+    args.iter().any(|a| a == flag)
+}
+
 fn main() {
     let args: Vec<String> = env::args().collect();
     if args.len() < 2 {
-        eprintln!("Usage: clean_rust <input_file>");
+        eprintln!("Usage: clean_rust <input_file_or_dir> [--jobs N] [--check|--dry-run] [--interactive]");
         std::process::exit(1);
     }
-    let input_path = &args[1];
-    let src = fs::read_to_string(input_path).expect("Failed to read file");
-    let mut syntax: File = syn::parse_file(&src).expect("Failed to parse Rust file");
-    Cleaner.visit_file_mut(&mut syntax);
-    let cleaned = prettyplease::unparse(&syntax);
-    fs::write(input_path, cleaned).expect("Failed to write cleaned file");
+    let input_path = PathBuf::from(&args[1]);
+    let jobs = parse_jobs_flag(&args);
+    let dry_run = has_flag(&args, "--check") || has_flag(&args, "--dry-run");
+    let interactive = has_flag(&args, "--interactive");
+
+    let mut files = Vec::new();
+    if let Err(e) = discover_rs_files(&input_path, &mut files) {
+        eprintln!("Failed to walk {}: {}", input_path.display(), e);
+        std::process::exit(1);
+    }
+
+    if interactive {
+        if let Err(e) = run_interactive(&files) {
+            eprintln!("interactive session failed: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let total = files.len();
+    let interrupted = install_interrupt_flag();
+    let results = clean_in_parallel(files, jobs, dry_run, interrupted.clone());
+
+    if dry_run {
+        report_dry_run(results);
+        return;
+    }
+
+    let mut cleaned = 0;
+    let mut errors = Vec::new();
+    let mut skipped = Vec::new();
+    for result in results {
+        match result {
+            FileResult::Cleaned => cleaned += 1,
+            FileResult::ParseError(path, msg) => errors.push((path, msg)),
+            FileResult::Skipped(path) => skipped.push(path),
+            FileResult::DryRun { .. } => unreachable!("dry_run was false"),
+        }
+    }
+
+    println!("Cleaned {}/{} files using {} job(s)", cleaned, total, jobs);
+    if !errors.is_empty() {
+        println!("Failed to clean {} file(s):", errors.len());
+        for (path, msg) in &errors {
+            println!("  {}: {}", path.display(), msg);
+        }
+    }
+    if interrupted.load(Ordering::SeqCst) {
+        println!("Interrupted: {} file(s) not yet processed:", skipped.len());
+        for path in &skipped {
+            println!("  {}", path.display());
+        }
+    }
+}
+
+/// Print every proposed diff plus a single JSON array of suggestions (one
+/// object per edit, each annotated with its source file), then exit non-zero
+/// if anything would change — the shape a CI "clean code" check wants.
+fn report_dry_run(results: Vec<FileResult>) {
+    let mut errors = Vec::new();
+    let mut skipped = Vec::new();
+    let mut json_entries = Vec::new();
+    let mut any_changes = false;
+
+    for result in results {
+        match result {
+            FileResult::DryRun {
+                path,
+                suggestions,
+                diff,
+            } => {
+                if !diff.is_empty() {
+                    any_changes = true;
+                    print!("{}", diff);
+                }
+                for suggestion in &suggestions {
+                    json_entries.push(format!(
+                        "{{\"file\":{},\"edit\":{}}}",
+                        json_string(&path.display().to_string()),
+                        suggestion.to_json()
+                    ));
+                }
+            }
+            FileResult::ParseError(path, msg) => errors.push((path, msg)),
+            FileResult::Skipped(path) => skipped.push(path),
+            FileResult::Cleaned => unreachable!("dry_run was true"),
+        }
+    }
+
+    println!("[{}]", json_entries.join(","));
+    if !errors.is_empty() {
+        eprintln!("Failed to check {} file(s):", errors.len());
+        for (path, msg) in &errors {
+            eprintln!("  {}: {}", path.display(), msg);
+        }
+    }
+    if !skipped.is_empty() {
+        eprintln!("Interrupted: {} file(s) not yet checked:", skipped.len());
+        for path in &skipped {
+            eprintln!("  {}", path.display());
+        }
+    }
+    if any_changes || !errors.is_empty() {
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prune(src: &str, used: &[&str]) -> Option<String> {
+        let tree: UseTree = syn::parse_str(src).unwrap();
+        let used: HashSet<String> = used.iter().map(|s| s.to_string()).collect();
+        prune_use_tree(&tree, &used, None).map(|t| t.to_token_stream().to_string())
+    }
+
+    #[test]
+    fn self_leaf_resolves_against_parent_module() {
+        // `io` is referenced, so the `self` leaf (which binds `io`, not a
+        // literal `self`) must survive even though `used` never mentions
+        // the string "self". `Write` is kept too, conservatively, since it's
+        // `UpperCamelCase` -- see `upper_camel_case_leaf_kept_even_if_unreferenced`.
+        assert_eq!(
+            prune("std::io::{self, Write}", &["io"]).as_deref(),
+            Some("std :: io :: { self , Write }")
+        );
+    }
+
+    #[test]
+    fn self_leaf_dropped_when_parent_module_unused() {
+        assert_eq!(prune("std::io::{self}", &[]), None);
+    }
+
+    #[test]
+    fn upper_camel_case_leaf_kept_even_if_unreferenced() {
+        // `Write` is never mentioned in `used` (e.g. it's only needed for
+        // trait-method resolution or macro expansion), but it's kept
+        // anyway since we can't prove it unused from syntax alone.
+        assert_eq!(
+            prune("std::io::Write", &[]).as_deref(),
+            Some("std :: io :: Write")
+        );
+    }
+
+    #[test]
+    fn renamed_upper_camel_case_leaf_kept_even_if_unreferenced() {
+        assert_eq!(
+            prune("std::fmt::Write as FmtWrite", &[]).as_deref(),
+            Some("std :: fmt :: Write as FmtWrite")
+        );
+    }
+
+    #[test]
+    fn lower_case_leaf_still_pruned_when_unused() {
+        assert_eq!(prune("std::mem::drop", &[]), None);
+    }
 }